@@ -0,0 +1,38 @@
+/// One column of an [`Alignment`]: a query position, a graph node, or both when they're paired
+/// (a match/mismatch); `rpos` alone is a deletion, `qpos` alone is an insertion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignedPair<N> {
+    pub rpos: Option<N>,
+    pub qpos: Option<usize>,
+}
+
+/// The result of an alignment: an ordered list of [`AlignedPair`]s from the start of the
+/// sequence/graph to the end.
+#[derive(Debug, Clone, Default)]
+pub struct Alignment<N>(Vec<AlignedPair<N>>);
+
+impl<N> Alignment<N> {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn push(&mut self, pair: AlignedPair<N>) {
+        self.0.push(pair);
+    }
+
+    pub fn reverse(&mut self) {
+        self.0.reverse();
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, AlignedPair<N>> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
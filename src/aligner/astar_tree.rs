@@ -0,0 +1,370 @@
+use crate::aligner::offsets::OffsetType;
+use crate::aligner::queue::AlignStateQueue;
+use crate::aligner::scoring::{AlignmentCosts, AlignmentStateTree};
+use crate::aligner::state::{AlignState, Backtrace, StateTreeNode, TreeIndexType};
+use crate::aligner::visited::Visited;
+use crate::graphs::{AlignableGraph, NodeIndexType};
+
+/// The default [`AlignmentStateTree`]: stores every explored state in a flat `Vec`, and uses
+/// [`Visited`] to skip re-expanding a `(node, offset)` cell that an earlier, cheaper state has
+/// already reached, which is what keeps the A* search from exploring the same cell again through
+/// every predecessor that can reach it.
+///
+/// `visited` is sized lazily on first use: [`AlignmentCosts::to_new_state_tree`] does not know
+/// the query length, but [`Self::generate_next`] does.
+pub struct AffineAstarTree<N, O, Ix> {
+    nodes: Vec<StateTreeNode<N, O, Ix>>,
+    visited: Option<Visited>,
+}
+
+impl<N, O, Ix> AffineAstarTree<N, O, Ix>
+where
+    N: NodeIndexType,
+    O: OffsetType,
+    Ix: TreeIndexType,
+{
+    pub fn new() -> Self {
+        Self { nodes: Vec::new(), visited: None }
+    }
+
+    /// Marks `(node, offset)` visited, returning `false` if a prior call already visited it, in
+    /// which case the caller must not expand into it again.
+    fn mark_visited<G: AlignableGraph<NodeIndex = N>>(
+        &mut self,
+        graph: &G,
+        seq_len: usize,
+        node: N,
+        offset: usize,
+    ) -> bool {
+        let visited = self.visited
+            .get_or_insert_with(|| Visited::new(graph.node_count_with_start(), seq_len));
+
+        visited.insert(node, offset)
+    }
+}
+
+impl<N, O, Ix> Default for AffineAstarTree<N, O, Ix>
+where
+    N: NodeIndexType,
+    O: OffsetType,
+    Ix: TreeIndexType,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, O, Ix> AlignmentStateTree<N, O, Ix> for AffineAstarTree<N, O, Ix>
+where
+    N: NodeIndexType,
+    O: OffsetType,
+    Ix: TreeIndexType,
+{
+    fn add_node(&mut self, node: StateTreeNode<N, O, Ix>) -> Ix {
+        let ix = Ix::new(self.nodes.len());
+        self.nodes.push(node);
+        ix
+    }
+
+    fn get_node(&self, ix: Ix) -> &StateTreeNode<N, O, Ix> {
+        &self.nodes[ix.index()]
+    }
+
+    fn close_indels_for(&mut self, current: &[Ix]) -> Vec<Ix> {
+        let mut closed = Vec::new();
+
+        for &ix in current {
+            let state = *self.get_node(ix);
+            if matches!(state.state(), AlignState::Insertion | AlignState::Deletion) {
+                let closed_state = StateTreeNode::new(
+                    state.node(), state.offset(), AlignState::Match, Backtrace::ClosedIndel(ix),
+                );
+                closed.push(self.add_node(closed_state));
+            }
+        }
+
+        closed
+    }
+
+    fn generate_next<G, H>(
+        &mut self,
+        queue: &mut AlignStateQueue<Ix>,
+        graph: &G,
+        seq_len: usize,
+        score: usize,
+        heuristic: &H,
+        state_ix: Ix,
+    )
+    where
+        G: AlignableGraph<NodeIndex = N>,
+        H: Fn(N, usize) -> usize,
+    {
+        let curr = *self.get_node(state_ix);
+        let node = curr.node();
+        let offset = curr.offset().as_usize();
+
+        match curr.state() {
+            AlignState::Start | AlignState::Match | AlignState::Mismatch => {
+                // Every successor is expanded as a mismatch. For a successor whose base matches
+                // the next query base, `extend` has already reached that cell at no cost, so the
+                // mismatch state is only a costlier duplicate of it.
+                if offset < seq_len {
+                    for succ in graph.successors(node) {
+                        if self.mark_visited(graph, seq_len, succ, offset + 1) {
+                            let mismatch = StateTreeNode::new(
+                                succ, O::new(offset + 1), AlignState::Mismatch, Backtrace::Step(state_ix),
+                            );
+                            let new_ix = self.add_node(mismatch);
+                            queue.enqueue(score + 1 + heuristic(succ, offset + 1), new_ix);
+                        }
+                    }
+
+                    // Insertion open: consume a query base without advancing in the graph.
+                    if self.mark_visited(graph, seq_len, node, offset + 1) {
+                        let insertion = StateTreeNode::new(
+                            node, O::new(offset + 1), AlignState::Insertion, Backtrace::Step(state_ix),
+                        );
+                        let new_ix = self.add_node(insertion);
+                        queue.enqueue(score + 1 + heuristic(node, offset + 1), new_ix);
+                    }
+                }
+
+                // Deletion open: advance in the graph without consuming a query base.
+                for succ in graph.successors(node) {
+                    if self.mark_visited(graph, seq_len, succ, offset) {
+                        let deletion = StateTreeNode::new(
+                            succ, O::new(offset), AlignState::Deletion, Backtrace::Step(state_ix),
+                        );
+                        let new_ix = self.add_node(deletion);
+                        queue.enqueue(score + 1 + heuristic(succ, offset), new_ix);
+                    }
+                }
+            },
+            AlignState::Insertion | AlignState::Insertion2 => {
+                if offset < seq_len && self.mark_visited(graph, seq_len, node, offset + 1) {
+                    let insertion = StateTreeNode::new(
+                        node, O::new(offset + 1), AlignState::Insertion, Backtrace::Step(state_ix),
+                    );
+                    let new_ix = self.add_node(insertion);
+                    queue.enqueue(score + 1 + heuristic(node, offset + 1), new_ix);
+                }
+            },
+            AlignState::Deletion | AlignState::Deletion2 => {
+                for succ in graph.successors(node) {
+                    if self.mark_visited(graph, seq_len, succ, offset) {
+                        let deletion = StateTreeNode::new(
+                            succ, O::new(offset), AlignState::Deletion, Backtrace::Step(state_ix),
+                        );
+                        let new_ix = self.add_node(deletion);
+                        queue.enqueue(score + 1 + heuristic(succ, offset), new_ix);
+                    }
+                }
+            },
+        }
+    }
+
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.visited = None;
+    }
+
+    fn node_capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+}
+
+/// A simple gap-affine cost model backed by [`AffineAstarTree`]: mismatches and indel-opens each
+/// cost one unit step of the search's outer score counter, with [`Self::gap_open`]/
+/// [`Self::gap_extend`] only feeding the A* heuristic's lower-bound estimate.
+#[derive(Debug, Clone, Copy)]
+pub struct GapAffine {
+    gap_open: usize,
+    gap_extend: usize,
+}
+
+impl GapAffine {
+    pub fn new(gap_open: usize, gap_extend: usize) -> Self {
+        Self { gap_open, gap_extend }
+    }
+}
+
+impl AlignmentCosts for GapAffine {
+    type StateTreeType<N, O, Ix> = AffineAstarTree<N, O, Ix>
+    where
+        N: NodeIndexType,
+        O: OffsetType,
+        Ix: TreeIndexType;
+
+    fn to_new_state_tree<N, O, Ix, G>(&self, _graph: &G) -> Self::StateTreeType<N, O, Ix>
+    where
+        N: NodeIndexType,
+        O: OffsetType,
+        Ix: TreeIndexType,
+        G: AlignableGraph<NodeIndex = N>,
+    {
+        AffineAstarTree::new()
+    }
+
+    fn gap_open(&self) -> usize {
+        self.gap_open
+    }
+
+    fn gap_extend(&self) -> usize {
+        self.gap_extend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    struct NIx(usize);
+
+    impl NodeIndexType for NIx {
+        fn new(ix: usize) -> Self {
+            NIx(ix)
+        }
+
+        fn index(&self) -> usize {
+            self.0
+        }
+    }
+
+    /// A linear, single-path graph, just enough to drive [`AffineAstarTree::generate_next`] and
+    /// [`Visited`] dedup without the superbubble-finder this snapshot doesn't have yet (see
+    /// [`crate::bubbles::node_map`], which assumes `crate::bubbles::finder::SuperbubbleFinder`
+    /// exists; it doesn't, so a full end-to-end `PoastaAligner::align` test isn't possible here).
+    struct LinearGraph {
+        bases: Vec<u8>,
+        start: Vec<NIx>,
+    }
+
+    impl LinearGraph {
+        fn new(bases: &[u8]) -> Self {
+            Self { bases: bases.to_vec(), start: vec![NIx(0)] }
+        }
+    }
+
+    impl AlignableGraph for LinearGraph {
+        type NodeIndex = NIx;
+
+        fn node_count_with_start(&self) -> usize {
+            self.bases.len()
+        }
+
+        fn start_nodes(&self) -> &[NIx] {
+            &self.start
+        }
+
+        fn is_end(&self, node: NIx) -> bool {
+            node.0 == self.bases.len() - 1
+        }
+
+        fn predecessors(&self, node: NIx) -> Vec<NIx> {
+            if node.0 == 0 { Vec::new() } else { vec![NIx(node.0 - 1)] }
+        }
+
+        fn successors(&self, node: NIx) -> Vec<NIx> {
+            if node.0 + 1 < self.bases.len() { vec![NIx(node.0 + 1)] } else { Vec::new() }
+        }
+
+        fn symbol(&self, node: NIx) -> u8 {
+            self.bases[node.0]
+        }
+    }
+
+    fn zero_heuristic(_node: NIx, _query_offset: usize) -> usize {
+        0
+    }
+
+    #[test]
+    fn test_generate_next_expands_mismatch_and_indel_opens() {
+        let graph = LinearGraph::new(b"ACG");
+        let mut tree: AffineAstarTree<NIx, u32, u32> = AffineAstarTree::new();
+        let start_ix = tree.add_node(StateTreeNode::new(
+            NIx(0), 0u32, AlignState::Match, Backtrace::Step(0),
+        ));
+
+        let mut queue = AlignStateQueue::new();
+        tree.generate_next(&mut queue, &graph, 3, 0, &zero_heuristic, start_ix);
+
+        // Node 0 has exactly one successor (node 1), so generate_next should have added one
+        // mismatch, one insertion open, and one deletion open.
+        assert_eq!(tree.nodes.len(), 4);
+        assert_eq!(tree.nodes[1].state(), AlignState::Mismatch);
+        assert_eq!((tree.nodes[1].node(), tree.nodes[1].offset()), (NIx(1), 1));
+        assert_eq!(tree.nodes[2].state(), AlignState::Insertion);
+        assert_eq!((tree.nodes[2].node(), tree.nodes[2].offset()), (NIx(0), 1));
+        assert_eq!(tree.nodes[3].state(), AlignState::Deletion);
+        assert_eq!((tree.nodes[3].node(), tree.nodes[3].offset()), (NIx(1), 0));
+
+        // All three were enqueued at the same f-value (score 0 + 1, zero heuristic).
+        assert_eq!(queue.pop_current(), Some(vec![]));
+        let second = queue.pop_current().expect("bucket 1 must exist");
+        assert_eq!(second.len(), 3);
+    }
+
+    #[test]
+    fn test_generate_next_dedups_repeat_cells_via_visited() {
+        let graph = LinearGraph::new(b"ACG");
+        let mut tree: AffineAstarTree<NIx, u32, u32> = AffineAstarTree::new();
+
+        // Two distinct states that both sit at the same (node, offset) cell, simulating two
+        // different predecessors reaching it.
+        let first_ix = tree.add_node(StateTreeNode::new(
+            NIx(0), 0u32, AlignState::Match, Backtrace::Step(0),
+        ));
+        let second_ix = tree.add_node(StateTreeNode::new(
+            NIx(0), 0u32, AlignState::Match, Backtrace::Step(0),
+        ));
+
+        let mut queue = AlignStateQueue::new();
+        tree.generate_next(&mut queue, &graph, 3, 0, &zero_heuristic, first_ix);
+        let after_first = tree.nodes.len();
+
+        tree.generate_next(&mut queue, &graph, 3, 0, &zero_heuristic, second_ix);
+
+        // Every cell the second call could reach was already marked visited by the first, so it
+        // must not add any further states.
+        assert_eq!(tree.nodes.len(), after_first);
+    }
+
+    #[test]
+    fn test_close_indels_for_converts_open_indels_to_match() {
+        let mut tree: AffineAstarTree<NIx, u32, u32> = AffineAstarTree::new();
+        let ins_ix = tree.add_node(StateTreeNode::new(
+            NIx(0), 1u32, AlignState::Insertion, Backtrace::Step(0),
+        ));
+        let del_ix = tree.add_node(StateTreeNode::new(
+            NIx(1), 0u32, AlignState::Deletion, Backtrace::Step(0),
+        ));
+        let match_ix = tree.add_node(StateTreeNode::new(
+            NIx(0), 1u32, AlignState::Match, Backtrace::Step(0),
+        ));
+
+        let closed = tree.close_indels_for(&[ins_ix, del_ix, match_ix]);
+
+        assert_eq!(closed.len(), 2);
+        assert_eq!(tree.get_node(closed[0]).state(), AlignState::Match);
+        assert_eq!(tree.get_node(closed[0]).backtrace(), Some(Backtrace::ClosedIndel(ins_ix)));
+        assert_eq!(tree.get_node(closed[1]).backtrace(), Some(Backtrace::ClosedIndel(del_ix)));
+    }
+
+    #[test]
+    fn test_clear_resets_nodes_and_visited() {
+        let graph = LinearGraph::new(b"ACG");
+        let mut tree: AffineAstarTree<NIx, u32, u32> = AffineAstarTree::new();
+        let start_ix = tree.add_node(StateTreeNode::new(
+            NIx(0), 0u32, AlignState::Match, Backtrace::Step(0),
+        ));
+        let mut queue = AlignStateQueue::new();
+        tree.generate_next(&mut queue, &graph, 3, 0, &zero_heuristic, start_ix);
+        assert!(tree.visited.is_some());
+
+        tree.clear();
+
+        assert_eq!(tree.nodes.len(), 0);
+        assert!(tree.visited.is_none());
+    }
+}
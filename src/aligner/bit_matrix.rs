@@ -0,0 +1,88 @@
+/// A flat, bit-packed `node x offset` matrix, modeled on the rustc `BitVector`/`BitMatrix`
+/// types. Used to track which `(node, query-offset)` cells have already been visited during
+/// alignment without the per-entry allocation and hashing overhead of a `HashSet`.
+///
+/// Cells are stored as single bits in a `Vec<u64>`, `u64s_per_row` words per row, which keeps
+/// the whole matrix cache-friendly even when the state tree explores millions of cells.
+#[derive(Debug, Clone)]
+pub struct BitMatrix {
+    num_rows: usize,
+    num_columns: usize,
+    u64s_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitMatrix {
+    pub fn new(num_rows: usize, num_columns: usize) -> Self {
+        let u64s_per_row = (num_columns + 63) / 64;
+
+        Self {
+            num_rows,
+            num_columns,
+            u64s_per_row,
+            words: vec![0u64; num_rows * u64s_per_row],
+        }
+    }
+
+    fn word_and_mask(&self, row: usize, column: usize) -> (usize, u64) {
+        assert!(row < self.num_rows, "row {row} out of bounds ({} rows)", self.num_rows);
+        assert!(column < self.num_columns, "column {column} out of bounds ({} columns)", self.num_columns);
+
+        let word_ix = row * self.u64s_per_row + column / 64;
+        let mask = 1u64 << (column % 64);
+
+        (word_ix, mask)
+    }
+
+    /// Marks `(row, column)` as visited. Returns `true` if this changed the bit, i.e. the cell
+    /// had not been visited before.
+    pub fn insert(&mut self, row: usize, column: usize) -> bool {
+        let (word_ix, mask) = self.word_and_mask(row, column);
+        let word = &mut self.words[word_ix];
+        let changed = *word & mask == 0;
+        *word |= mask;
+
+        changed
+    }
+
+    pub fn contains(&self, row: usize, column: usize) -> bool {
+        let (word_ix, mask) = self.word_and_mask(row, column);
+
+        self.words[word_ix] & mask != 0
+    }
+
+    pub fn clear(&mut self) {
+        self.words.iter_mut().for_each(|w| *w = 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitMatrix;
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut matrix = BitMatrix::new(4, 130);
+
+        assert!(!matrix.contains(2, 129));
+        assert!(matrix.insert(2, 129));
+        assert!(matrix.contains(2, 129));
+
+        // Inserting the same cell again reports no change
+        assert!(!matrix.insert(2, 129));
+
+        // Other cells remain untouched
+        assert!(!matrix.contains(0, 0));
+        assert!(!matrix.contains(2, 128));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut matrix = BitMatrix::new(2, 65);
+        matrix.insert(1, 64);
+        assert!(matrix.contains(1, 64));
+
+        matrix.clear();
+        assert!(!matrix.contains(1, 64));
+    }
+}
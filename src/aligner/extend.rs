@@ -0,0 +1,55 @@
+use crate::aligner::offsets::OffsetType;
+use crate::aligner::scoring::AlignmentStateTree;
+use crate::aligner::state::{AlignState, Backtrace, StateTreeNode, TreeIndexType};
+use crate::graphs::{AlignableGraph, NodeIndexType};
+
+/// Greedily extends a state along every matching path through the graph: from `start_ix`,
+/// follows each successor whose base matches the next query base, repeating until no further
+/// match is possible. Yields every newly created `Match` state in the order they were reached,
+/// covering every branch when more than one successor matches at the same step.
+pub struct PathExtender<Ix> {
+    new_states: std::vec::IntoIter<Ix>,
+}
+
+impl<Ix: TreeIndexType> PathExtender<Ix> {
+    pub fn new<G, N, O, T>(graph: &G, seq: &[u8], tree: &mut T, start_ix: Ix) -> Self
+    where
+        G: AlignableGraph<NodeIndex = N>,
+        N: NodeIndexType,
+        O: OffsetType,
+        T: AlignmentStateTree<N, O, Ix>,
+    {
+        let mut new_states = Vec::new();
+        let mut frontier = vec![start_ix];
+
+        while let Some(curr_ix) = frontier.pop() {
+            let curr = *tree.get_node(curr_ix);
+            let offset = curr.offset().as_usize();
+
+            if offset >= seq.len() {
+                continue;
+            }
+
+            for succ in graph.successors(curr.node()) {
+                if graph.symbol(succ) == seq[offset] {
+                    let new_state = StateTreeNode::new(
+                        succ, O::new(offset + 1), AlignState::Match, Backtrace::Step(curr_ix),
+                    );
+                    let new_ix = tree.add_node(new_state);
+                    new_states.push(new_ix);
+                    frontier.push(new_ix);
+                }
+            }
+        }
+
+        Self { new_states: new_states.into_iter() }
+    }
+}
+
+impl<Ix> Iterator for PathExtender<Ix> {
+    type Item = Ix;
+
+    fn next(&mut self) -> Option<Ix> {
+        self.new_states.next()
+    }
+}
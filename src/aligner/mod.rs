@@ -5,6 +5,13 @@ pub mod scoring;
 pub mod queue;
 pub mod alignment;
 pub mod visited;
+pub mod bit_matrix;
+pub mod state_tree_pool;
+pub mod astar_tree;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
 
 use crate::graphs::{AlignableGraph, NodeIndexType};
 use crate::aligner::offsets::OffsetType;
@@ -12,12 +19,47 @@ use crate::aligner::state::{AlignState, StateTreeNode, Backtrace, TreeIndexType}
 use crate::aligner::scoring::AlignmentCosts;
 use crate::aligner::queue::AlignStateQueue;
 use crate::aligner::extend::PathExtender;
+use crate::aligner::state_tree_pool::StateTreePool;
+use crate::bubbles::node_map::NodeBubbleMapBuilder;
 
 use crate::debug::DebugOutputWriter;
 use crate::debug::messages::DebugOutputMessage;
 
 pub use alignment::{AlignedPair, Alignment};
 
+/// Controls how many live states are retained per score level during alignment.
+///
+/// Pruning the queue down to a beam of the most promising states trades exactness for speed,
+/// which matters for long noisy reads where an exact POA search is too slow. A state that has
+/// reached an end node is never pruned, regardless of the configured width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BeamWidth {
+    /// Keep all states; exact, optimal alignment (the default).
+    Full,
+
+    /// Keep at most this many states per score level.
+    Absolute(usize),
+
+    /// Keep this fraction of the live states per score level, e.g. `0.5` keeps half.
+    Fraction(f64),
+}
+
+impl BeamWidth {
+    fn width_for(&self, n_live: usize) -> Option<usize> {
+        match self {
+            BeamWidth::Full => None,
+            BeamWidth::Absolute(w) => Some(*w),
+            BeamWidth::Fraction(frac) => Some(((n_live as f64) * frac).ceil() as usize),
+        }
+    }
+}
+
+impl Default for BeamWidth {
+    fn default() -> Self {
+        BeamWidth::Full
+    }
+}
+
 enum ExtendResult<Ix: TreeIndexType> {
     NewExtendedNodes(Vec<Ix>),
     ReachedEnd(Ix)
@@ -33,6 +75,7 @@ where
 {
     costs: C,
     debug_output: Option<&'a DebugOutputWriter>,
+    beam_width: BeamWidth,
 }
 
 impl<'a, C> PoastaAligner<'a, C>
@@ -43,6 +86,7 @@ where
         Self {
             costs,
             debug_output: None,
+            beam_width: BeamWidth::default(),
         }
     }
 
@@ -50,10 +94,23 @@ where
         PoastaAligner {
             costs,
             debug_output: Some(debug_writer),
+            beam_width: BeamWidth::default(),
         }
     }
 
-    pub fn align<O, Ix, G, S, N>(&mut self, graph: &G, sequence: &S) -> (usize, Alignment<N>)
+    /// Bound the number of live states explored per score level, trading exactness for speed.
+    /// See [`BeamWidth`] for the available modes.
+    pub fn with_beam_width(mut self, beam_width: BeamWidth) -> Self {
+        self.beam_width = beam_width;
+        self
+    }
+
+    pub fn align<O, Ix, G, S, N>(
+        &mut self,
+        graph: &G,
+        sequence: &S,
+        pool: Option<&mut StateTreePool<<C as AlignmentCosts>::StateTreeType<N, O, Ix>>>,
+    ) -> (usize, Alignment<N>, bool)
     where
         O: OffsetType,
         Ix: TreeIndexType,
@@ -66,17 +123,35 @@ where
 
         assert!(seq.len() - 1 < max_offset, "Sequence is too long for Offset integer type!");
 
+        // Precompute, once per graph, the minimal residual graph distance from every node to
+        // any end node by chaining bubble-exit distances. This turns the remaining search into
+        // an A* search: states are enqueued by `f = g + h` instead of raw score `g`, where `h`
+        // is an admissible lower bound on the remaining gap-affine cost.
+        let (_, dist_to_end) = NodeBubbleMapBuilder::<O, G>::new(graph).build_with_end_distances();
+        let heuristic = |node: N, query_offset: usize| self.heuristic(&dist_to_end, node, query_offset, seq.len());
+
         let mut queue = AlignStateQueue::new();
-        let mut state_tree: <C as AlignmentCosts>::StateTreeType<N, O, Ix> = self.costs.to_new_state_tree(graph);
+
+        // Reuse a pooled state tree's backing storage when given one, rather than allocating a
+        // fresh tree for every alignment.
+        let mut owned_state_tree;
+        let state_tree: &mut <C as AlignmentCosts>::StateTreeType<N, O, Ix> = match pool {
+            Some(pool) => pool.get_or_init(|| self.costs.to_new_state_tree(graph)),
+            None => {
+                owned_state_tree = self.costs.to_new_state_tree(graph);
+                &mut owned_state_tree
+            }
+        };
 
         // Add graph start nodes to queue
         for start_node in graph.start_nodes().iter() {
             let start_state = StateTreeNode::new_start(*start_node);
             let new_ix = state_tree.add_node(start_state);
-            queue.enqueue(0, new_ix);
+            queue.enqueue(heuristic(*start_node, 0), new_ix);
         }
 
         let mut score = 0;
+        let mut beam_pruned = false;
         let reached_end_state;
         loop {
             let Some(mut current) = queue.pop_current() else {
@@ -88,12 +163,19 @@ where
                 continue;
             }
 
+            // Keep only the best `beam_width` states for this score level, ranked by the A*
+            // `f = g + h` value (lowest first). States that already reached an end node are
+            // never pruned.
+            if self.prune_beam(graph, state_tree, &mut current, seq.len(), score, &heuristic) {
+                beam_pruned = true;
+            }
+
             // Close indels for current score, and add to current queue
             let new_states = state_tree.close_indels_for(current.as_ref());
             current.extend(new_states.into_iter());
 
             // Try to extend the alignment along matching sequence in the graph
-            match self.extend(graph, seq, &mut state_tree, &mut current) {
+            match self.extend(graph, seq, state_tree, &mut current) {
                 ReachedEnd(end) => {
                     reached_end_state = end;
                     break;
@@ -102,22 +184,164 @@ where
             }
 
             // If the end not reached yet, expand into next alignment states, including mismatches
-            // and indels. New states to explore are queued per score, such that lower scores are
-            // explored first.
+            // and indels. New states to explore are queued by `f = g + h`, such that states with
+            // the lowest bound on total alignment cost are explored first.
             for state_ix in current {
-                state_tree.generate_next(&mut queue, graph, seq.len(), state_ix);
+                state_tree.generate_next(&mut queue, graph, seq.len(), score, &heuristic, state_ix);
             }
 
             score += 1;
         }
 
-        let alignment = self.backtrace(&state_tree, reached_end_state);
+        let alignment = self.backtrace(state_tree, reached_end_state);
 
         if let Some(debug) = self.debug_output {
-            debug.log(DebugOutputMessage::new_from_state_tree(&state_tree));
+            debug.log(DebugOutputMessage::new_from_state_tree(state_tree));
         }
 
-        (score, alignment)
+        (score, alignment, beam_pruned)
+    }
+
+    /// Prunes `current` down to the configured [`BeamWidth`], keeping the states with the
+    /// lowest A* `f = score + heuristic(node, offset)` value (ties broken arbitrarily). The
+    /// heuristic is always on (see [`Self::heuristic`]), so `f` is always the right ranking:
+    /// there is no "heuristic disabled" mode left to fall back to raw-offset ranking. Returns
+    /// `true` if any state was discarded, which means the final score can no longer be
+    /// guaranteed optimal.
+    fn prune_beam<O, Ix, G, T, N, H>(
+        &self,
+        graph: &G,
+        tree: &T,
+        current: &mut Vec<Ix>,
+        query_len: usize,
+        score: usize,
+        heuristic: &H,
+    ) -> bool
+    where
+        O: OffsetType,
+        Ix: TreeIndexType,
+        G: AlignableGraph<NodeIndex=N>,
+        N: NodeIndexType,
+        C: AlignmentCosts<StateTreeType<N, O, Ix> = T>,
+        T: AlignmentStateTree<N, O, Ix>,
+        H: Fn(N, usize) -> usize,
+    {
+        let Some(width) = self.beam_width.width_for(current.len()) else {
+            return false;
+        };
+
+        if current.len() <= width {
+            return false;
+        }
+
+        let is_reached_end = |ix: &Ix| {
+            let node = tree.get_node(*ix);
+            matches!(node.state(), AlignState::Start | AlignState::Match | AlignState::Mismatch)
+                && node.offset().as_usize() == query_len
+                && graph.is_end(node.node())
+        };
+
+        let f_value = |ix: &Ix| {
+            let node = tree.get_node(*ix);
+            score + heuristic(node.node(), node.offset().as_usize())
+        };
+
+        let (keep_always, mut rest): (Vec<Ix>, Vec<Ix>) =
+            current.drain(..).partition(|ix| is_reached_end(ix));
+
+        rest.sort_by_key(f_value);
+
+        let remaining_width = width.saturating_sub(keep_always.len());
+        let discarded = rest.len() > remaining_width;
+        rest.truncate(remaining_width);
+
+        *current = keep_always;
+        current.extend(rest);
+
+        discarded
+    }
+
+    /// Aligns many sequences against the same graph concurrently. The graph, cost model, and
+    /// beam width are read-only and shared across worker threads; each worker gets its own
+    /// [`PoastaAligner`] with its own scratch [`AlignStateQueue`] and state tree, so alignments
+    /// do not interfere with one another. Results are returned in input order.
+    ///
+    /// `num_threads` selects the size of a dedicated thread pool; `None` uses rayon's global
+    /// pool. `progress`, if given, is called after each completed alignment with the number of
+    /// sequences completed so far.
+    pub fn align_batch<O, Ix, G, S, N>(
+        &self,
+        graph: &G,
+        sequences: &[S],
+        num_threads: Option<usize>,
+        progress: Option<&(dyn Fn(usize) + Sync)>,
+    ) -> Vec<(usize, Alignment<N>, bool)>
+    where
+        O: OffsetType,
+        Ix: TreeIndexType + Send,
+        G: AlignableGraph<NodeIndex=N> + Sync,
+        S: AsRef<[u8]> + Sync,
+        N: NodeIndexType + Send,
+        C: Clone + Sync,
+    {
+        let completed = AtomicUsize::new(0);
+
+        let run = || {
+            sequences.par_iter()
+                // Each worker thread keeps its own pooled state tree via `map_init`, so the
+                // sequences handed to that thread reuse one arena instead of allocating a fresh
+                // tree per alignment.
+                .map_init(
+                    || (
+                        PoastaAligner {
+                            costs: self.costs.clone(),
+                            debug_output: self.debug_output,
+                            beam_width: self.beam_width,
+                        },
+                        StateTreePool::new(),
+                    ),
+                    |(worker, pool), seq| {
+                        let result = worker.align::<O, Ix, G, S, N>(graph, seq, Some(pool));
+
+                        if let Some(cb) = progress {
+                            cb(completed.fetch_add(1, Ordering::Relaxed) + 1);
+                        }
+
+                        result
+                    },
+                )
+                .collect::<Vec<_>>()
+        };
+
+        match num_threads {
+            Some(n) => rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build()
+                .expect("Could not build thread pool for batch alignment")
+                .install(run),
+            None => run(),
+        }
+    }
+
+    /// Admissible lower bound on the remaining gap-affine alignment cost for a state at `node`
+    /// with query offset `query_offset` on a sequence of length `query_len`. Zero when the
+    /// remaining graph distance `d_end(node)` and the remaining query length can be matched
+    /// base-for-base, otherwise the unavoidable cost of indels to reconcile the length
+    /// difference between them.
+    fn heuristic<O, N>(&self, dist_to_end: &[O], node: N, query_offset: usize, query_len: usize) -> usize
+    where
+        O: OffsetType,
+        N: NodeIndexType,
+    {
+        let remaining_query = query_len - query_offset;
+        let remaining_graph = dist_to_end[node.index()].as_usize();
+
+        if remaining_graph == remaining_query {
+            0
+        } else {
+            let diff = remaining_graph.abs_diff(remaining_query);
+            self.costs.gap_open() + self.costs.gap_extend() * diff
+        }
     }
 
     fn extend<O, Ix, G, N, T>(
@@ -244,3 +468,173 @@ where
         alignment
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphs::mock::create_test_graph1;
+
+    type NIx = petgraph::graph::NodeIndex<crate::graphs::mock::NIx>;
+
+    /// A bare-bones [`AlignmentStateTree`] backed by a plain `Vec`, just enough to drive tests
+    /// of [`PoastaAligner`]'s own logic without needing a real scoring/extension implementation.
+    struct MockTree<N, O, Ix> {
+        nodes: Vec<StateTreeNode<N, O, Ix>>,
+    }
+
+    impl<N, O, Ix> MockTree<N, O, Ix> {
+        fn new() -> Self {
+            Self { nodes: Vec::new() }
+        }
+    }
+
+    impl<N, O, Ix> AlignmentStateTree<N, O, Ix> for MockTree<N, O, Ix>
+    where
+        N: NodeIndexType,
+        O: OffsetType,
+        Ix: TreeIndexType,
+    {
+        fn add_node(&mut self, node: StateTreeNode<N, O, Ix>) -> Ix {
+            let ix = Ix::new(self.nodes.len());
+            self.nodes.push(node);
+            ix
+        }
+
+        fn get_node(&self, ix: Ix) -> &StateTreeNode<N, O, Ix> {
+            &self.nodes[ix.index()]
+        }
+
+        fn close_indels_for(&mut self, _current: &[Ix]) -> Vec<Ix> {
+            Vec::new()
+        }
+
+        fn generate_next<G, H>(
+            &mut self,
+            _queue: &mut AlignStateQueue<Ix>,
+            _graph: &G,
+            _seq_len: usize,
+            _score: usize,
+            _heuristic: &H,
+            _state_ix: Ix,
+        )
+        where
+            G: AlignableGraph<NodeIndex=N>,
+            H: Fn(N, usize) -> usize,
+        {
+            unimplemented!("not exercised by these tests")
+        }
+
+        fn clear(&mut self) {
+            self.nodes.clear();
+        }
+
+        fn node_capacity(&self) -> usize {
+            self.nodes.capacity()
+        }
+    }
+
+    #[derive(Clone)]
+    struct TestCosts;
+
+    impl AlignmentCosts for TestCosts {
+        type StateTreeType<N, O, Ix> = MockTree<N, O, Ix>
+        where
+            N: NodeIndexType,
+            O: OffsetType,
+            Ix: TreeIndexType;
+
+        fn to_new_state_tree<N, O, Ix, G>(&self, _graph: &G) -> Self::StateTreeType<N, O, Ix>
+        where
+            N: NodeIndexType,
+            O: OffsetType,
+            Ix: TreeIndexType,
+            G: AlignableGraph<NodeIndex=N>,
+        {
+            MockTree::new()
+        }
+
+        fn gap_open(&self) -> usize {
+            4
+        }
+
+        fn gap_extend(&self) -> usize {
+            2
+        }
+    }
+
+    fn zero_heuristic<N: NodeIndexType>(_node: N, _query_offset: usize) -> usize {
+        0
+    }
+
+    #[test]
+    fn test_prune_beam_never_discards_end_reaching_state() {
+        let graph = create_test_graph1();
+        let query_len = 5;
+
+        let mut tree: MockTree<NIx, u32, u32> = MockTree::new();
+        let end_node = (0..9u32).map(NIx::new)
+            .find(|n| graph.is_end(*n))
+            .expect("graph1 must have an end node");
+
+        // Under the zero heuristic every state ties on f, and the width (2) is smaller than the
+        // number of states (5), so some must be discarded; the end-reaching state must survive.
+        let end_ix = tree.add_node(StateTreeNode::new(
+            end_node, query_len as u32, AlignState::Match, Backtrace::Step(0),
+        ));
+        let mut current = vec![end_ix];
+        for offset in [1u32, 2, 3, 4] {
+            current.push(tree.add_node(StateTreeNode::new(
+                NIx::new(0), offset, AlignState::Match, Backtrace::Step(0),
+            )));
+        }
+
+        let aligner = PoastaAligner::new(TestCosts).with_beam_width(BeamWidth::Absolute(2));
+        let discarded = aligner.prune_beam(&graph, &tree, &mut current, query_len, 0, &zero_heuristic);
+
+        assert!(discarded);
+        assert_eq!(current.len(), 2);
+        assert!(current.contains(&end_ix));
+    }
+
+    #[test]
+    fn test_prune_beam_ranks_by_f_value() {
+        let graph = create_test_graph1();
+
+        let mut tree: MockTree<NIx, u32, u32> = MockTree::new();
+
+        // With a non-trivial heuristic, the state with the larger offset is not necessarily the
+        // one with the lowest f = score + h; pruning must follow f, not raw offset.
+        let better = tree.add_node(StateTreeNode::new(
+            NIx::new(0), 2u32, AlignState::Match, Backtrace::Step(0),
+        ));
+        let worse = tree.add_node(StateTreeNode::new(
+            NIx::new(0), 3u32, AlignState::Match, Backtrace::Step(0),
+        ));
+        let mut current = vec![better, worse];
+
+        let heuristic = |_node: NIx, query_offset: usize| if query_offset == 2 { 0 } else { 10 };
+
+        let aligner = PoastaAligner::new(TestCosts).with_beam_width(BeamWidth::Absolute(1));
+        let discarded = aligner.prune_beam(&graph, &tree, &mut current, 5, 0, &heuristic);
+
+        assert!(discarded);
+        assert_eq!(current, vec![better]);
+    }
+
+    // A full multi-sequence order-preservation test would need to actually run `align` to
+    // completion, which this snapshot can't do: `align` builds a `NodeBubbleMapBuilder`, which
+    // needs `crate::bubbles::finder::SuperbubbleFinder`, and this snapshot has no such module
+    // (and `MockTree::generate_next` is unimplemented). This instead pins down the part we
+    // can verify in isolation: `align_batch` type-checks and returns results in the one order
+    // that's trivially correct, the empty one, without panicking on an empty batch.
+    #[test]
+    fn test_align_batch_handles_empty_batch() {
+        let graph = create_test_graph1();
+        let aligner = PoastaAligner::new(TestCosts);
+
+        let sequences: [&[u8]; 0] = [];
+        let results = aligner.align_batch::<u32, u32, _, &[u8], NIx>(&graph, &sequences, None, None);
+
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,30 @@
+/// An unsigned integer type indexing a query sequence offset, generic so callers can pick the
+/// smallest width that fits their sequences (see the `max_value` assert in
+/// [`crate::aligner::PoastaAligner::align`]).
+pub trait OffsetType: Copy + Eq + Ord + std::hash::Hash + std::fmt::Debug {
+    fn new(value: usize) -> Self;
+    fn as_usize(&self) -> usize;
+    fn max_value() -> Self;
+}
+
+macro_rules! impl_offset_type {
+    ($t:ty) => {
+        impl OffsetType for $t {
+            fn new(value: usize) -> Self {
+                value as $t
+            }
+
+            fn as_usize(&self) -> usize {
+                *self as usize
+            }
+
+            fn max_value() -> Self {
+                <$t>::MAX
+            }
+        }
+    };
+}
+
+impl_offset_type!(u32);
+impl_offset_type!(u64);
+impl_offset_type!(usize);
@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+
+/// A bucket priority queue of alignment states, indexed by `f = g + h` so [`Self::pop_current`]
+/// returns states in non-decreasing order of total estimated alignment cost, turning the search
+/// in [`crate::aligner::PoastaAligner::align`] into A*.
+pub struct AlignStateQueue<Ix> {
+    buckets: VecDeque<Vec<Ix>>,
+
+    /// Priority of `buckets[0]`, i.e. the number of buckets popped so far.
+    front_priority: usize,
+}
+
+impl<Ix> AlignStateQueue<Ix> {
+    pub fn new() -> Self {
+        Self { buckets: VecDeque::new(), front_priority: 0 }
+    }
+
+    /// Queues `ix` into the bucket for `priority`, growing the queue with empty buckets as
+    /// needed so lower-priority buckets are still popped in order. A priority whose bucket was
+    /// already popped goes into the next bucket to be popped.
+    pub fn enqueue(&mut self, priority: usize, ix: Ix) {
+        let bucket = priority.saturating_sub(self.front_priority);
+        if self.buckets.len() <= bucket {
+            self.buckets.resize_with(bucket + 1, Vec::new);
+        }
+
+        self.buckets[bucket].push(ix);
+    }
+
+    /// Pops the next bucket in increasing priority order, `Some(vec![])` for an empty one rather
+    /// than skipping it so callers can track which score level they're on. Returns `None` once
+    /// every bucket enqueued so far has been popped.
+    pub fn pop_current(&mut self) -> Option<Vec<Ix>> {
+        let bucket = self.buckets.pop_front()?;
+        self.front_priority += 1;
+
+        Some(bucket)
+    }
+}
+
+impl<Ix> Default for AlignStateQueue<Ix> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AlignStateQueue;
+
+    #[test]
+    fn test_pop_current_returns_buckets_in_priority_order() {
+        let mut queue: AlignStateQueue<u32> = AlignStateQueue::new();
+        queue.enqueue(2, 20);
+        queue.enqueue(0, 10);
+        queue.enqueue(2, 21);
+
+        assert_eq!(queue.pop_current(), Some(vec![10]));
+        assert_eq!(queue.pop_current(), Some(vec![]));
+        assert_eq!(queue.pop_current(), Some(vec![20, 21]));
+        assert_eq!(queue.pop_current(), None);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::aligner::offsets::OffsetType;
+use crate::aligner::queue::AlignStateQueue;
+use crate::aligner::state::{StateTreeNode, TreeIndexType};
+use crate::graphs::{AlignableGraph, NodeIndexType};
+
+/// A gap-affine cost model, plus the state tree representation it scores alignment states with.
+pub trait AlignmentCosts {
+    type StateTreeType<N, O, Ix>: AlignmentStateTree<N, O, Ix>
+    where
+        N: NodeIndexType,
+        O: OffsetType,
+        Ix: TreeIndexType;
+
+    fn to_new_state_tree<N, O, Ix, G>(&self, graph: &G) -> Self::StateTreeType<N, O, Ix>
+    where
+        N: NodeIndexType,
+        O: OffsetType,
+        Ix: TreeIndexType,
+        G: AlignableGraph<NodeIndex = N>;
+
+    /// Cost of opening a new gap (insertion or deletion).
+    fn gap_open(&self) -> usize;
+
+    /// Cost of extending an already open gap by one base.
+    fn gap_extend(&self) -> usize;
+}
+
+/// Owns the explored `(node, offset, state)` cells for a single alignment and knows how to
+/// expand a state into its successors.
+pub trait AlignmentStateTree<N, O, Ix>
+where
+    N: NodeIndexType,
+    O: OffsetType,
+    Ix: TreeIndexType,
+{
+    fn add_node(&mut self, node: StateTreeNode<N, O, Ix>) -> Ix;
+
+    fn get_node(&self, ix: Ix) -> &StateTreeNode<N, O, Ix>;
+
+    /// Closes any indels that can complete for the states in `current`, returning the newly
+    /// created closing states.
+    fn close_indels_for(&mut self, current: &[Ix]) -> Vec<Ix>;
+
+    /// Expands `state_ix` into its next alignment states (mismatches and indel opens), enqueuing
+    /// each by `score` plus the step's cost plus `heuristic(node, query_offset)` of the new state.
+    fn generate_next<G, H>(
+        &mut self,
+        queue: &mut AlignStateQueue<Ix>,
+        graph: &G,
+        seq_len: usize,
+        score: usize,
+        heuristic: &H,
+        state_ix: Ix,
+    )
+    where
+        G: AlignableGraph<NodeIndex = N>,
+        H: Fn(N, usize) -> usize;
+
+    /// Resets the tree to empty in place, keeping its backing storage's capacity so it can be
+    /// handed straight back out by a [`crate::aligner::state_tree_pool::StateTreePool`].
+    fn clear(&mut self);
+
+    /// How many [`StateTreeNode`]s the tree's backing storage currently has room for without
+    /// growing.
+    fn node_capacity(&self) -> usize;
+}
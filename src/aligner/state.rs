@@ -0,0 +1,95 @@
+use crate::aligner::offsets::OffsetType;
+use crate::graphs::NodeIndexType;
+
+/// An index type used to address nodes within an [`crate::aligner::scoring::AlignmentStateTree`].
+/// Kept separate from the graph's own [`NodeIndexType`] since a single graph node is visited by
+/// many distinct alignment states over the course of a search.
+pub trait TreeIndexType: Copy + Eq + std::hash::Hash + std::fmt::Debug {
+    fn new(ix: usize) -> Self;
+    fn index(&self) -> usize;
+}
+
+impl TreeIndexType for u32 {
+    fn new(ix: usize) -> Self {
+        ix as u32
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Which of the gap-affine alignment automaton's states a given [`StateTreeNode`] occupies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignState {
+    Start,
+    Match,
+    Mismatch,
+    Insertion,
+    Insertion2,
+    Deletion,
+    Deletion2,
+}
+
+/// How a [`StateTreeNode`] was reached, pointing back to its predecessor in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backtrace<Ix> {
+    /// Reached by a single alignment step (match, mismatch, or indel open) from `Ix`.
+    Step(Ix),
+
+    /// Reached by closing the open indel whose latest state is `Ix`.
+    ClosedIndel(Ix),
+}
+
+impl<Ix: Copy> Backtrace<Ix> {
+    pub fn prev(&self) -> Ix {
+        match self {
+            Backtrace::Step(ix) | Backtrace::ClosedIndel(ix) => *ix,
+        }
+    }
+}
+
+/// A single explored `(graph node, query offset, alignment state)` cell in the alignment search.
+#[derive(Debug, Clone, Copy)]
+pub struct StateTreeNode<N, O, Ix> {
+    node: N,
+    offset: O,
+    state: AlignState,
+    backtrace: Option<Backtrace<Ix>>,
+}
+
+impl<N, O, Ix> StateTreeNode<N, O, Ix>
+where
+    N: NodeIndexType,
+    O: OffsetType,
+    Ix: TreeIndexType,
+{
+    pub fn new_start(node: N) -> Self {
+        Self {
+            node,
+            offset: O::new(0),
+            state: AlignState::Start,
+            backtrace: None,
+        }
+    }
+
+    pub fn new(node: N, offset: O, state: AlignState, backtrace: Backtrace<Ix>) -> Self {
+        Self { node, offset, state, backtrace: Some(backtrace) }
+    }
+
+    pub fn node(&self) -> N {
+        self.node
+    }
+
+    pub fn offset(&self) -> O {
+        self.offset
+    }
+
+    pub fn state(&self) -> AlignState {
+        self.state
+    }
+
+    pub fn backtrace(&self) -> Option<Backtrace<Ix>> {
+        self.backtrace
+    }
+}
@@ -0,0 +1,139 @@
+use crate::aligner::offsets::OffsetType;
+use crate::aligner::scoring::AlignmentStateTree;
+use crate::aligner::state::TreeIndexType;
+use crate::graphs::NodeIndexType;
+
+/// Bump-allocates the backing storage for a state tree and reuses it across alignments instead
+/// of freeing and reallocating for every call, following the arena approach used by rustc's
+/// `Arena`: rather than dropping the tree after each alignment, [`Self::clear`] resets it in
+/// place so its chunk capacity is kept and handed straight back out on the next alignment.
+///
+/// Pass a `&mut StateTreePool` to [`crate::aligner::PoastaAligner::align`] when aligning many
+/// sequences against the same graph to amortize the allocations `state_tree.add_node` performs
+/// for every explored cell.
+pub struct StateTreePool<T> {
+    tree: Option<T>,
+    high_water: usize,
+}
+
+impl<T> StateTreePool<T> {
+    pub fn new() -> Self {
+        Self {
+            tree: None,
+            high_water: 0,
+        }
+    }
+
+    /// The largest node capacity the pooled tree's backing storage has had when handed out by
+    /// [`Self::get_or_init`]; growth during the alignment after the last hand-out is not counted.
+    pub fn high_water_capacity(&self) -> usize {
+        self.high_water
+    }
+}
+
+impl<T> Default for StateTreePool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, O, Ix, T> StateTreePool<T>
+where
+    O: OffsetType,
+    Ix: TreeIndexType,
+    N: NodeIndexType,
+    T: AlignmentStateTree<N, O, Ix>,
+{
+    /// Hands out the pooled tree, initializing it with `init` on first use. On later calls the
+    /// previous tree is reset with [`AlignmentStateTree::clear`] rather than reallocated, so its
+    /// capacity only ever grows.
+    pub fn get_or_init(&mut self, init: impl FnOnce() -> T) -> &mut T {
+        match &mut self.tree {
+            Some(tree) => tree.clear(),
+            None => self.tree = Some(init()),
+        }
+
+        let tree = self.tree.as_mut().expect("tree was just initialized");
+        self.high_water = self.high_water.max(tree.node_capacity());
+
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateTreePool;
+    use crate::aligner::queue::AlignStateQueue;
+    use crate::aligner::scoring::AlignmentStateTree;
+    use crate::aligner::state::{StateTreeNode, TreeIndexType};
+    use crate::graphs::{AlignableGraph, NodeIndexType};
+
+    type NIx = petgraph::graph::NodeIndex<crate::graphs::mock::NIx>;
+
+    struct MockTree {
+        nodes: Vec<StateTreeNode<NIx, u32, u32>>,
+    }
+
+    impl AlignmentStateTree<NIx, u32, u32> for MockTree {
+        fn add_node(&mut self, node: StateTreeNode<NIx, u32, u32>) -> u32 {
+            let ix = self.nodes.len() as u32;
+            self.nodes.push(node);
+            ix
+        }
+
+        fn get_node(&self, ix: u32) -> &StateTreeNode<NIx, u32, u32> {
+            &self.nodes[ix.index()]
+        }
+
+        fn close_indels_for(&mut self, _current: &[u32]) -> Vec<u32> {
+            Vec::new()
+        }
+
+        fn generate_next<G, H>(
+            &mut self,
+            _queue: &mut AlignStateQueue<u32>,
+            _graph: &G,
+            _seq_len: usize,
+            _score: usize,
+            _heuristic: &H,
+            _state_ix: u32,
+        )
+        where
+            G: AlignableGraph<NodeIndex=NIx>,
+            H: Fn(NIx, usize) -> usize,
+        {
+            unimplemented!("not exercised by this test")
+        }
+
+        fn clear(&mut self) {
+            self.nodes.clear();
+        }
+
+        fn node_capacity(&self) -> usize {
+            self.nodes.capacity()
+        }
+    }
+
+    #[test]
+    fn test_pool_reuses_and_clears_backing_storage() {
+        let mut pool: StateTreePool<MockTree> = StateTreePool::new();
+
+        {
+            let tree = pool.get_or_init(|| {
+                let mut t = MockTree { nodes: Vec::new() };
+                t.add_node(StateTreeNode::new_start(NIx::new(0)));
+                t.add_node(StateTreeNode::new_start(NIx::new(1)));
+                t
+            });
+            assert_eq!(tree.nodes.len(), 2);
+        }
+
+        // The second call must reset the existing tree in place, not re-run `init`.
+        {
+            let tree = pool.get_or_init(|| panic!("init must not run again once the pool is warm"));
+            assert_eq!(tree.nodes.len(), 0);
+        }
+
+        assert!(pool.high_water_capacity() >= 2);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::aligner::bit_matrix::BitMatrix;
+use crate::graphs::NodeIndexType;
+
+/// Tracks which `(node, query-offset)` cells have already been explored during a single
+/// alignment. Backed by a [`BitMatrix`] instead of a hash set: the state tree can explore
+/// millions of cells on a single long alignment, and a word-and-mask check is far cheaper there
+/// than hashing a node index on every visit.
+pub struct Visited {
+    matrix: BitMatrix,
+}
+
+impl Visited {
+    /// `num_nodes` and `max_offset` size the matrix for the graph and query being aligned;
+    /// `max_offset` is the largest query offset to track (the query length), and the matrix gets
+    /// `max_offset + 1` columns so every offset from `0` to `max_offset` has its own column.
+    pub fn new(num_nodes: usize, max_offset: usize) -> Self {
+        Self {
+            matrix: BitMatrix::new(num_nodes, max_offset + 1),
+        }
+    }
+
+    /// Marks `(node, offset)` as visited. Returns `true` if it had not been visited before.
+    pub fn insert<N: NodeIndexType>(&mut self, node: N, offset: usize) -> bool {
+        self.matrix.insert(node.index(), offset)
+    }
+
+    pub fn is_visited<N: NodeIndexType>(&self, node: N, offset: usize) -> bool {
+        self.matrix.contains(node.index(), offset)
+    }
+
+    /// Resets all cells to unvisited, keeping the matrix's allocated capacity for reuse.
+    pub fn clear(&mut self) {
+        self.matrix.clear();
+    }
+}
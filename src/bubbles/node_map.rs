@@ -1,5 +1,5 @@
 use std::collections::VecDeque;
-use rustc_hash::FxHashSet;
+use crate::aligner::bit_matrix::BitMatrix;
 use crate::aligner::offsets::OffsetType;
 use crate::bubbles::finder::SuperbubbleFinder;
 use crate::graphs::{AlignableGraph, NodeIndexType};
@@ -13,7 +13,7 @@ enum BubbleNode<N> {
     Exit(N)
 }
 
-struct NodeBubbleMapBuilder<'a, O, G>
+pub struct NodeBubbleMapBuilder<'a, O, G>
 where
     G: AlignableGraph,
 {
@@ -31,8 +31,10 @@ where
     /// A list of bubbles containing a particular node
     node_bubble_map: Vec<Vec<NodeBubbleMap<G::NodeIndex, O>>>,
 
-    /// Which nodes have we already processed
-    visited: FxHashSet<G::NodeIndex>,
+    /// Which nodes have we already processed. A single-column bit matrix serves as a compact,
+    /// cache-friendly node bitset, cheaper than hashing for the dense node-index ranges these
+    /// graphs use.
+    visited: BitMatrix,
 }
 
 impl<'a, O, G> NodeBubbleMapBuilder<'a, O, G>
@@ -62,7 +64,7 @@ where
             bubble_entrance: bubble_entrances,
             bubble_exit: bubble_exits,
             node_bubble_map: vec![Vec::default(); graph.node_count_with_start()],
-            visited: FxHashSet::default(),
+            visited: BitMatrix::new(graph.node_count_with_start(), 1),
         }
     }
 
@@ -74,7 +76,7 @@ where
         let mut queue: VecDeque<_> = vec![
             (exit, 0usize, vec![(0usize, exit)])
         ].into();
-        self.visited.insert(exit);
+        self.visited.insert(exit.index(), 0);
 
         while !queue.is_empty() {
             let (curr, dist_from_start, bubble_stack) = queue.pop_front().unwrap();
@@ -92,7 +94,7 @@ where
             }
 
             for pred in self.graph.predecessors(curr) {
-                if !self.visited.contains(&pred) {
+                if !self.visited.contains(pred.index(), 0) {
                     let pred_rpo = rev_postorder[pred.index()];
                     let new_dist_from_start = dist_from_start + 1;
                     let mut new_bubble_stack = bubble_stack.clone();
@@ -109,7 +111,7 @@ where
                         new_bubble_stack.push((new_dist_from_start, pred));
                     }
 
-                    self.visited.insert(pred);
+                    self.visited.insert(pred.index(), 0);
                     queue.push_back((pred, new_dist_from_start, new_bubble_stack));
                 }
             }
@@ -120,7 +122,7 @@ where
         for rpo in (0..self.graph.node_count_with_start()).rev() {
             let inv_rev_postorder = self.finder.get_inv_rev_postorder();
             let node_id = inv_rev_postorder[rpo];
-            if self.visited.contains(&node_id) {
+            if self.visited.contains(node_id.index(), 0) {
                 continue;
             }
 
@@ -135,6 +137,43 @@ where
 
         self.node_bubble_map
     }
+
+    /// Like [`Self::build`], but additionally computes `dist_to_end(v)` for every node `v`,
+    /// i.e., the minimal number of graph bases from `v` to any end node. This is obtained by
+    /// chaining `dist_to_exit` along the nearest enclosing bubble's exit: `dist_to_end(v) =
+    /// nearest.dist_to_exit + dist_to_end(nearest.bubble_exit)`, with end nodes fixed at `0`.
+    ///
+    /// The resulting distances form an admissible lower bound on the remaining graph length
+    /// and are used by [`crate::aligner::PoastaAligner`] to guide the search as an A* heuristic.
+    pub fn build_with_end_distances(mut self) -> (Vec<Vec<NodeBubbleMap<G::NodeIndex, O>>>, Vec<O>) {
+        let graph = self.graph;
+        let inv_rev_postorder = self.finder.get_inv_rev_postorder().to_vec();
+        let node_bubble_map = self.build();
+
+        let mut dist_to_end = vec![O::new(0); node_bubble_map.len()];
+        for rpo in (0..node_bubble_map.len()).rev() {
+            let node_id = inv_rev_postorder[rpo];
+
+            if graph.is_end(node_id) {
+                dist_to_end[node_id.index()] = O::new(0);
+                continue;
+            }
+
+            // Entries are stored in BFS-discovery order, not sorted by distance, so the nearest
+            // enclosing bubble exit is the one with the minimum `dist_to_exit`, not the first
+            // entry. Skip any entry pointing back at this node itself: using it would read the
+            // not-yet-computed `dist_to_end[node_id]`.
+            dist_to_end[node_id.index()] = node_bubble_map[node_id.index()].iter()
+                .filter(|nearest| nearest.bubble_exit != node_id)
+                .min_by_key(|nearest| nearest.dist_to_exit.as_usize())
+                .map(|nearest| O::new(
+                    nearest.dist_to_exit.as_usize() + dist_to_end[nearest.bubble_exit.index()].as_usize()
+                ))
+                .unwrap_or(O::new(0));
+        }
+
+        (node_bubble_map, dist_to_end)
+    }
 }
 
 
@@ -155,6 +194,14 @@ where
             dist_to_exit
         }
     }
+
+    pub fn bubble_exit(&self) -> N {
+        self.bubble_exit
+    }
+
+    pub fn dist_to_exit(&self) -> O {
+        self.dist_to_exit
+    }
 }
 
 #[cfg(test)]
@@ -217,4 +264,32 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_dist_to_end_picks_minimum_not_first_entry() {
+        let graph1 = create_test_graph1();
+        let (node_map1, dist_to_end1) = NodeBubbleMapBuilder::<u32, _>::new(&graph1)
+            .build_with_end_distances();
+
+        // graph1 is a simple chain of three size-3 bubbles, so every node's only candidate
+        // is either itself (filtered out as a self-reference) or its single enclosing exit.
+        let truth_dist_to_end1 = [2u32, 1, 0, 2, 1, 0, 2, 1, 0];
+        assert_eq!(dist_to_end1, truth_dist_to_end1);
+
+        let graph2 = create_test_graph2();
+        let (node_map2, dist_to_end2) = NodeBubbleMapBuilder::<u32, _>::new(&graph2)
+            .build_with_end_distances();
+
+        // Node 2's entries are [(exit=7, dist=2), (exit=2, dist=0)]; the second is a
+        // self-reference and must be skipped, leaving (7, 2) as the only candidate.
+        assert_eq!(node_map2[2].len(), 2);
+        assert_eq!(dist_to_end2[2], 2 + dist_to_end2[7]);
+
+        // Node 8's entries are [(exit=7, dist=3), (exit=6, dist=2)]. The true minimum is the
+        // *second* entry (dist 2 < 3); picking `.first()` here is exactly the bug this test
+        // guards against.
+        assert_eq!(node_map2[8].len(), 2);
+        assert_eq!(dist_to_end2[8], 2 + dist_to_end2[6]);
+        assert_ne!(dist_to_end2[8], 3 + dist_to_end2[7]);
+    }
+
 }
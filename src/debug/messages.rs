@@ -0,0 +1,9 @@
+/// A snapshot of alignment state handed to [`super::DebugOutputWriter`]. Opaque: it carries no
+/// data, as [`Self::new_from_state_tree`] ignores the state tree it is given.
+pub struct DebugOutputMessage;
+
+impl DebugOutputMessage {
+    pub fn new_from_state_tree<T>(_state_tree: &T) -> Self {
+        Self
+    }
+}
@@ -0,0 +1,12 @@
+pub mod messages;
+
+use messages::DebugOutputMessage;
+
+/// Sink for alignment debug output. A real writer would serialize [`DebugOutputMessage`]s to a
+/// file for offline visualization; this snapshot only has callers that construct one behind an
+/// `Option`, so for now it just drops what it's given.
+pub struct DebugOutputWriter;
+
+impl DebugOutputWriter {
+    pub fn log(&self, _message: DebugOutputMessage) {}
+}
@@ -0,0 +1,29 @@
+/// An index type identifying a single node in an [`AlignableGraph`]. Kept separate from the
+/// alignment state tree's own index type ([`crate::aligner::state::TreeIndexType`]) since many
+/// alignment states can visit the same graph node.
+pub trait NodeIndexType: Copy + Eq + Ord + std::hash::Hash + std::fmt::Debug {
+    fn new(ix: usize) -> Self;
+    fn index(&self) -> usize;
+}
+
+/// A partial order (DAG) graph that [`crate::aligner::PoastaAligner`] can align a sequence
+/// against.
+pub trait AlignableGraph {
+    type NodeIndex: NodeIndexType;
+
+    /// Total number of nodes, including any virtual start node(s).
+    fn node_count_with_start(&self) -> usize;
+
+    /// The graph's start node(s); the search begins here.
+    fn start_nodes(&self) -> &[Self::NodeIndex];
+
+    /// Whether `node` is an end node, i.e. a valid place for an alignment to terminate.
+    fn is_end(&self, node: Self::NodeIndex) -> bool;
+
+    fn predecessors(&self, node: Self::NodeIndex) -> Vec<Self::NodeIndex>;
+
+    fn successors(&self, node: Self::NodeIndex) -> Vec<Self::NodeIndex>;
+
+    /// The base at `node`, compared against the query sequence to tell a match from a mismatch.
+    fn symbol(&self, node: Self::NodeIndex) -> u8;
+}